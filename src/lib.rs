@@ -4,41 +4,153 @@ use std::{
     path::{Path, PathBuf},
 };
 
+mod platform;
+
+/// Errors that can occur while resolving a PID's controlling TTY.
+#[derive(Debug, thiserror::Error)]
+pub enum TtyError {
+    /// `pid` was not a valid process id (e.g. `-1`).
+    #[error("pid {0} is not a valid process id")]
+    InvalidPid(i32),
+    /// The platform backend failed to determine the device number of the
+    /// controlling TTY, e.g. `/proc/{pid}/stat` couldn't be read, or the
+    /// `sysctl(KERN_PROC)` call failed.
+    #[error("failed to determine the controlling TTY device for pid {pid}")]
+    DeviceLookup {
+        pid: i32,
+        #[source]
+        source: std::io::Error,
+    },
+    /// The kernel reports `tty_nr == 0` for this PID (on macOS/FreeBSD: a
+    /// `NODEV` terminal device in `kinfo_proc`), meaning it has no
+    /// controlling TTY (common for daemons).
+    #[error("pid {0} has no controlling TTY")]
+    NoCtty(i32),
+    /// No entry in `/proc/tty/drivers` matches the resolved major/minor.
+    #[error("no tty driver matches major={major} minor={minor}")]
+    NoMatchingDriver { major: i64, minor: i64 },
+    /// The device path could not be resolved. On Linux: a driver matched, but
+    /// neither path-guessing heuristic nor the `ttyname(3)` fallback could
+    /// resolve an existing, verified device path. On macOS/FreeBSD:
+    /// `devname(3)` found no name for the device, or the resulting path
+    /// doesn't exist.
+    #[error("could not resolve the device path of the controlling TTY")]
+    PathGuessFailed,
+    /// There is no controlling-TTY backend implemented for this target OS.
+    #[error("no controlling-TTY backend is implemented for this platform")]
+    UnsupportedPlatform,
+}
+
+/// The kind of terminal a `TtyDriver` serves, derived from the trailing
+/// `type` column of `/proc/tty/drivers` (e.g. `system:/dev/tty`, `serial`,
+/// `pty:slave`, `console`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TtyDriverKind {
+    /// `system:<subtype>`, e.g. `system:/dev/tty` or `system:console`.
+    System(String),
+    /// A virtual console, e.g. the `unknown`/`/dev/tty` line typed `console`.
+    Console,
+    /// A real serial line.
+    Serial,
+    /// The pseudo-terminal master side (`pty:master`).
+    PtyMaster,
+    /// The pseudo-terminal slave side (`pty:slave`).
+    PtySlave,
+    /// A type string we don't recognize.
+    Unknown,
+}
+
+impl TtyDriverKind {
+    fn parse(raw: &str) -> Self {
+        match raw.split_once(':') {
+            Some(("system", subtype)) => TtyDriverKind::System(subtype.to_string()),
+            Some(("pty", "master")) => TtyDriverKind::PtyMaster,
+            Some(("pty", "slave")) => TtyDriverKind::PtySlave,
+            _ => match raw {
+                "system" => TtyDriverKind::System(String::new()),
+                "console" => TtyDriverKind::Console,
+                "serial" => TtyDriverKind::Serial,
+                _ => TtyDriverKind::Unknown,
+            },
+        }
+    }
+}
+
+/// A single entry from `/proc/tty/drivers`, matched against a PID's
+/// controlling TTY.
+///
+/// This classification is Linux-only: `/proc/tty/drivers` doesn't exist on
+/// macOS/FreeBSD, so [`find_tty_driver_for_pid`] always returns `None`
+/// there, even though [`find_tty_for_pid`] can still resolve a path via
+/// `devname(3)` on those platforms.
 #[derive(Debug, Clone)]
-struct TtyDriver {
-    path: PathBuf,
-    major: i32,
-    minor_range: RangeInclusive<i32>,
+pub struct TtyDriver {
+    /// The driver name, e.g. `rfcomm` or `pty_slave` (1st column).
+    pub name: String,
+    /// The device path prefix, e.g. `/dev/pts` (2nd column).
+    pub path: PathBuf,
+    /// The parsed type of terminal this driver serves (5th column).
+    pub kind: TtyDriverKind,
+    major: i64,
+    minor_range: RangeInclusive<i64>,
 }
 
 impl TtyDriver {
     /// Trys to find the TTY for a given process ID.
-    /// This is unfortunately not straight forward. We have to do:
-    /// 1. Read the tty_nr from /proc/<PID>/stat and do some bit-magic to get major and minor
+    ///
+    /// The actual resolution is entirely platform-specific and delegated to
+    /// `platform::resolve_path_for_pid`: on Linux that means parsing
+    /// `/proc/tty/drivers` (see `resolve_tty` below), while macOS/FreeBSD
+    /// resolve the path directly from the `dev_t` via `devname(3)`.
+    fn find_tty_for_pid(pid: i32) -> Result<PathBuf, TtyError> {
+        log::info!("Start finding TTY for {pid}");
+        platform::resolve_path_for_pid(pid)
+    }
+
+    /// Linux-specific resolution steps that `platform::linux` feeds its
+    /// `dev_t` into once it has read `tty_nr` from `/proc/<pid>/stat` (step 1)
+    /// and decoded it into major and minor:
     /// 2. Read /proc/tty/drivers to see which path corresponds to which major number and minor range
     /// 3. Match those 2 together and find a fitting driver
     /// 4. 'Guess' the resulting path (e.g. either /dev/tty/2 or /dev/tty2)
     /// 5. Verify the guess is correct by stat-ing the result and comparing major and minor
-    fn find_tty_for_pid(pid: i32) -> Option<PathBuf> {
-        log::info!("Start finding TTY for {pid}");
-        // 1. Parse major and minor tty_nr
-        let (tty_major, tty_minor) = TtyDriver::get_tty_nr_for_pid(pid)?;
+    ///
+    /// Returns both the matched driver and the guessed/verified device path,
+    /// so callers can either locate the TTY or classify it.
+    pub(crate) fn resolve_tty(dev: i64) -> Result<(TtyDriver, PathBuf), TtyError> {
+        let (tty_major, tty_minor) = TtyDriver::decode_dev(dev);
+        log::info!("Got major/minor numbers: tty_major: {tty_major}, tty_minor: {tty_minor}");
+
         // 2. Parse /proc/tty/drivers
         let drivers = TtyDriver::parse_tty_drivers();
         // 3. Find a match
-        let driver = TtyDriver::match_drivers_to_tty_nr(drivers, tty_major, tty_minor)?;
+        let driver = TtyDriver::match_drivers_to_tty_nr(drivers, tty_major, tty_minor)
+            .ok_or(TtyError::NoMatchingDriver {
+                major: tty_major,
+                minor: tty_minor,
+            })?;
         // 4. and 5. Guess and verify path
-        let path = TtyDriver::guess_tty_path(&driver.path, tty_major, tty_minor)?;
+        let path = TtyDriver::guess_tty_path(&driver.path, tty_major, tty_minor)
+            .ok_or(TtyError::PathGuessFailed)?;
         log::info!("Step 4: {path:?}");
 
-        Some(path)
+        Ok((driver, path))
+    }
+
+    /// Decodes a 32-bit device word into (major, minor), using the full
+    /// glibc/kernel encoding (see `MAJOR`/`MINOR` in `bits/sysmacros.h`).
+    /// This is wider than the naive `dev >> 8` / `dev & 0xff` split, which
+    /// truncates any minor above 255 - exactly the case for the
+    /// `pty_slave`/`pty_master` drivers, whose minor ranges span `0-1048575`.
+    fn decode_dev(dev: i64) -> (i64, i64) {
+        let major = (dev >> 8) & 0xfff;
+        let minor = ((dev >> 12) & 0xfff00) | (dev & 0xff);
+        (major, minor)
     }
 
-    fn verify_tty_path(path: &Path, tty_major: i32, tty_minor: i32) -> bool {
+    fn verify_tty_path(path: &Path, tty_major: i64, tty_minor: i64) -> bool {
         if let Ok(metadata) = path.metadata() {
-            let rdev = metadata.rdev() as i32;
-            let dev_major = rdev >> 8;
-            let dev_minor = rdev & 0xff;
+            let (dev_major, dev_minor) = TtyDriver::decode_dev(metadata.rdev() as i64);
             if dev_major == tty_major && dev_minor == tty_minor {
                 return true;
             }
@@ -46,7 +158,7 @@ impl TtyDriver {
         false
     }
 
-    fn guess_tty_path(path: &Path, tty_major: i32, tty_minor: i32) -> Option<PathBuf> {
+    fn guess_tty_path(path: &Path, tty_major: i64, tty_minor: i64) -> Option<PathBuf> {
         log::info!("Trying to guess the TTY-path");
         // First, guess seperated by slash: (e.g. /dev/tty/2)
         let mut res = path.join(format!("{tty_minor}"));
@@ -66,14 +178,22 @@ impl TtyDriver {
             return Some(res);
         }
 
+        // Neither heuristic path shape matched. Fall back to asking the
+        // current process directly via ttyname(3), in case the device node
+        // just doesn't follow either convention.
+        if let Some(res) = ttyname_fallback(tty_major, tty_minor) {
+            log::info!("Found and verified {res:?} via ttyname(3)");
+            return Some(res);
+        }
+
         // No luck
         None
     }
 
     fn match_drivers_to_tty_nr(
         drivers: Vec<TtyDriver>,
-        tty_major: i32,
-        tty_minor: i32,
+        tty_major: i64,
+        tty_minor: i64,
     ) -> Option<TtyDriver> {
         log::info!("Trying to find a matching driver");
 
@@ -107,12 +227,13 @@ impl TtyDriver {
 
         for line in drivers_raw.lines() {
             let parts: Vec<_> = line.split_whitespace().collect();
-            if parts.len() < 4 {
+            if parts.len() < 5 {
                 // Something is wrong. Silently ignore this entry
                 continue;
             }
+            let name = parts[0].to_string();
             let path = PathBuf::from(parts[1]);
-            let major = match parts[2].parse::<i32>() {
+            let major = match parts[2].parse::<i64>() {
                 Ok(maj) => maj,
                 Err(_) => continue,
             };
@@ -123,8 +244,11 @@ impl TtyDriver {
                     continue;
                 }
             };
+            let kind = TtyDriverKind::parse(parts[4]);
             let driver = TtyDriver {
+                name,
                 path,
+                kind,
                 major,
                 minor_range,
             };
@@ -135,57 +259,91 @@ impl TtyDriver {
     }
 
     // Getting either "3" or "3-10" and parsing a Range from that
-    fn parse_minor_range(tty_minor: &str) -> Option<RangeInclusive<i32>> {
+    fn parse_minor_range(tty_minor: &str) -> Option<RangeInclusive<i64>> {
         let minor_range: Vec<_> = tty_minor.split('-').collect();
         if minor_range.len() == 1 {
-            let start = minor_range[0].parse::<i32>().ok()?;
+            let start = minor_range[0].parse::<i64>().ok()?;
             Some(start..=start)
         } else if minor_range.len() == 2 {
-            let start = minor_range[0].parse::<i32>().ok()?;
-            let end = minor_range[1].parse::<i32>().ok()?;
+            let start = minor_range[0].parse::<i64>().ok()?;
+            let end = minor_range[1].parse::<i64>().ok()?;
             Some(start..=end)
         } else {
             None
         }
     }
+}
+
+/// Calls `ttyname_r(3)` on `fd`, returning the terminal path if `fd` refers
+/// to one.
+fn ttyname_for_fd(fd: i32) -> Option<PathBuf> {
+    let mut buf = [0_u8; libc::PATH_MAX as usize];
+    let ret = unsafe { libc::ttyname_r(fd, buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return None;
+    }
+    let name = std::ffi::CStr::from_bytes_until_nul(&buf).ok()?;
+    Some(PathBuf::from(name.to_string_lossy().into_owned()))
+}
 
-    fn get_tty_nr_for_pid(pid: i32) -> Option<(i32, i32)> {
-        if pid == -1 {
-            log::info!("Invalid PID");
-            return None;
+/// Tries `ttyname_r` on fds 0/1/2 of the current process and returns the
+/// first path that both exists and verifies against `tty_major`/`tty_minor`.
+fn ttyname_fallback(tty_major: i64, tty_minor: i64) -> Option<PathBuf> {
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        if let Some(path) = ttyname_for_fd(fd) {
+            if TtyDriver::verify_tty_path(&path, tty_major, tty_minor) {
+                return Some(path);
+            }
         }
-        let procfile = PathBuf::from(format!("/proc/{pid}/stat"));
-        let stat = std::fs::read_to_string(&procfile).ok()?;
-
-        let tty_nr = stat
-            .split_whitespace()
-            .nth(6)
-            .and_then(|s| s.parse::<i32>().ok())?;
-        // from /usr/include/linux/kdev_t.h
-        // #define MAJOR(dev)	((dev)>>8)
-        // #define MINOR(dev)	((dev) & 0xff)
-        let tty_major = tty_nr >> 8;
-        let tty_minor = tty_nr & 0xff;
-
-        log::info!(
-            "Got major/minor numbers from {}: tty_major: {tty_major}, tty_minor: {tty_minor}",
-            procfile.to_string_lossy()
-        );
-        Some((tty_major, tty_minor))
     }
+    None
 }
 
-pub fn find_tty_for_pid(pid: i32) -> Option<PathBuf> {
+/// Finds the controlling TTY for `pid`.
+pub fn find_tty_for_pid(pid: i32) -> Result<PathBuf, TtyError> {
     TtyDriver::find_tty_for_pid(pid)
 }
 
+/// Backward-compatible, `Option`-returning thin wrapper around
+/// [`find_tty_for_pid`] for callers that don't care why resolution failed.
+pub fn find_tty_for_pid_opt(pid: i32) -> Option<PathBuf> {
+    find_tty_for_pid(pid).ok()
+}
+
+/// Resolves the controlling TTY of the *current* process directly via
+/// `ttyname(3)`, without a PID round-trip through `/proc`. This still works
+/// when stdin/stdout have been redirected to a pipe, as long as one of
+/// fd 0/1/2 is still a tty.
+pub fn ctty_of_current_process() -> Option<PathBuf> {
+    [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO]
+        .into_iter()
+        .find_map(ttyname_for_fd)
+}
+
+/// Like [`find_tty_for_pid`], but returns the matched [`TtyDriver`] instead
+/// of just its resolved device path, so callers can tell whether a PID's
+/// terminal is a real serial line, a pseudo-terminal slave, a virtual
+/// console, etc.
+///
+/// Linux-only: it classifies via `/proc/tty/drivers`, which doesn't exist
+/// on macOS/FreeBSD, so this always returns `None` there even when
+/// [`find_tty_for_pid`] successfully resolves a path.
+pub fn find_tty_driver_for_pid(pid: i32) -> Option<TtyDriver> {
+    let dev = platform::get_dev_for_pid(pid).ok()?;
+    TtyDriver::resolve_tty(dev).ok().map(|(driver, _)| driver)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn decode_dev_does_not_truncate_large_minor() {
+        // pty_slave/pty_master span minor 0-1048575, well above the 0xff
+        // that a naive `dev & 0xff` split would allow.
+        let major = 136;
+        let minor = 1000;
+        let dev = (major << 8) | ((minor & 0xff) | ((minor & !0xff) << 12));
+        assert_eq!(TtyDriver::decode_dev(dev), (major, minor));
     }
 }
@@ -0,0 +1,76 @@
+//! Shared `sysctl(KERN_PROC)` + `devname(3)` plumbing for the macOS and
+//! FreeBSD backends. The two platforms only disagree on where `tdev` lives
+//! in `kinfo_proc` and on the "no ctty" sentinel, so each backend just reads
+//! its own field out of the `kinfo_proc` this returns.
+
+use std::ffi::CStr;
+use std::io;
+use std::mem;
+use std::path::PathBuf;
+use std::ptr;
+
+use crate::TtyError;
+
+/// Runs `sysctl(CTL_KERN, KERN_PROC, KERN_PROC_PID, pid)` and returns the
+/// raw `kinfo_proc`.
+pub(crate) fn kinfo_proc_for_pid(pid: i32) -> Result<libc::kinfo_proc, TtyError> {
+    if pid == -1 {
+        log::info!("Invalid PID");
+        return Err(TtyError::InvalidPid(pid));
+    }
+
+    let mut mib: [libc::c_int; 4] = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_PID, pid];
+    let mut info: libc::kinfo_proc = unsafe { mem::zeroed() };
+    let mut size = mem::size_of::<libc::kinfo_proc>();
+
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as u32,
+            &mut info as *mut _ as *mut libc::c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        log::info!("sysctl(KERN_PROC_PID) failed for pid {pid}");
+        return Err(TtyError::DeviceLookup {
+            pid,
+            source: io::Error::last_os_error(),
+        });
+    }
+
+    Ok(info)
+}
+
+/// Resolves the controlling TTY path for `pid` via `devname(3)`, which maps
+/// a `dev_t` straight to its `/dev` entry name - unlike `/proc/tty/drivers`,
+/// which is Linux-only and doesn't exist on BSD/macOS, and unlike the Linux
+/// `MAJOR`/`MINOR` bit layout decoded by `TtyDriver::decode_dev`, which does
+/// not match how these platforms pack major/minor into a `dev_t`.
+pub(crate) fn resolve_path_via_devname(pid: i32, dev: i64) -> Result<PathBuf, TtyError> {
+    let mut buf = [0_u8; 32];
+    let name_ptr = unsafe {
+        libc::devname_r(
+            dev as libc::dev_t,
+            libc::S_IFCHR,
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.len() as i32,
+        )
+    };
+    if name_ptr.is_null() {
+        log::info!("devname_r could not resolve a device name for pid {pid}");
+        return Err(TtyError::PathGuessFailed);
+    }
+
+    let name = unsafe { CStr::from_ptr(name_ptr) }.to_string_lossy().into_owned();
+    let path = PathBuf::from("/dev").join(name);
+    if !path.exists() {
+        log::info!("devname_r resolved {path:?} for pid {pid}, but it doesn't exist");
+        return Err(TtyError::PathGuessFailed);
+    }
+
+    log::info!("Resolved {path:?} via devname(3) for pid {pid}");
+    Ok(path)
+}
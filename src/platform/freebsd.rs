@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use crate::TtyError;
+
+use super::bsd_common::{kinfo_proc_for_pid, resolve_path_via_devname};
+
+/// Reads the controlling terminal's `dev_t` out of the `kinfo_proc` sysctl's
+/// `ki_tdev` field.
+pub(crate) fn get_dev_for_pid(pid: i32) -> Result<i64, TtyError> {
+    let info = kinfo_proc_for_pid(pid)?;
+
+    let tdev = info.ki_tdev;
+    if tdev as i64 == libc::NODEV as i64 {
+        log::info!("PID {pid} has no controlling TTY");
+        return Err(TtyError::NoCtty(pid));
+    }
+
+    log::info!("Got ki_tdev for pid {pid}: {tdev}");
+    Ok(tdev as i64)
+}
+
+pub(crate) fn resolve_path_for_pid(pid: i32) -> Result<PathBuf, TtyError> {
+    let dev = get_dev_for_pid(pid)?;
+    resolve_path_via_devname(pid, dev)
+}
@@ -0,0 +1,45 @@
+use std::io;
+use std::path::PathBuf;
+
+use crate::TtyError;
+
+/// Reads the raw `tty_nr` field (7th column) out of `/proc/<pid>/stat`.
+pub(crate) fn get_dev_for_pid(pid: i32) -> Result<i64, TtyError> {
+    if pid == -1 {
+        log::info!("Invalid PID");
+        return Err(TtyError::InvalidPid(pid));
+    }
+    let procfile = PathBuf::from(format!("/proc/{pid}/stat"));
+    let stat = std::fs::read_to_string(&procfile)
+        .map_err(|source| TtyError::DeviceLookup { pid, source })?;
+
+    let tty_nr = stat
+        .split_whitespace()
+        .nth(6)
+        .and_then(|s| s.parse::<i64>().ok())
+        .ok_or_else(|| TtyError::DeviceLookup {
+            pid,
+            source: io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed /proc/{pid}/stat"),
+            ),
+        })?;
+
+    if tty_nr == 0 {
+        log::info!("PID {pid} has no controlling TTY");
+        return Err(TtyError::NoCtty(pid));
+    }
+
+    log::info!(
+        "Got tty_nr from {}: {tty_nr}",
+        procfile.to_string_lossy()
+    );
+    Ok(tty_nr)
+}
+
+/// Resolves the controlling TTY path for `pid` by matching its `tty_nr`
+/// against `/proc/tty/drivers`.
+pub(crate) fn resolve_path_for_pid(pid: i32) -> Result<PathBuf, TtyError> {
+    let dev = get_dev_for_pid(pid)?;
+    crate::TtyDriver::resolve_tty(dev).map(|(_, path)| path)
+}
@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use crate::TtyError;
+
+use super::bsd_common::{kinfo_proc_for_pid, resolve_path_via_devname};
+
+/// Reads the controlling terminal's `dev_t` out of the `kinfo_proc` sysctl,
+/// mirroring what `ps` and the `ctty` crate do on macOS.
+pub(crate) fn get_dev_for_pid(pid: i32) -> Result<i64, TtyError> {
+    let info = kinfo_proc_for_pid(pid)?;
+
+    let tdev = info.kp_eproc.e_tdev;
+    if tdev == -1 {
+        log::info!("PID {pid} has no controlling TTY");
+        return Err(TtyError::NoCtty(pid));
+    }
+
+    log::info!("Got e_tdev for pid {pid}: {tdev}");
+    Ok(tdev as i64)
+}
+
+pub(crate) fn resolve_path_for_pid(pid: i32) -> Result<PathBuf, TtyError> {
+    let dev = get_dev_for_pid(pid)?;
+    resolve_path_via_devname(pid, dev)
+}
@@ -0,0 +1,33 @@
+//! Per-OS resolution of a process's controlling TTY.
+//!
+//! Every backend exposes two things:
+//! - `get_dev_for_pid`: the raw `dev_t` word of the controlling terminal.
+//! - `resolve_path_for_pid`: the actual `/dev` path for that PID's
+//!   controlling TTY. How that's derived from the `dev_t` differs per OS -
+//!   Linux matches it against `/proc/tty/drivers` (see `TtyDriver::resolve_tty`
+//!   in `lib.rs`), while macOS/FreeBSD ask the kernel directly via
+//!   `devname(3)` - so each backend implements its own route rather than
+//!   sharing one.
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub(crate) use linux::{get_dev_for_pid, resolve_path_for_pid};
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+mod bsd_common;
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+pub(crate) use macos::{get_dev_for_pid, resolve_path_for_pid};
+
+#[cfg(target_os = "freebsd")]
+mod freebsd;
+#[cfg(target_os = "freebsd")]
+pub(crate) use freebsd::{get_dev_for_pid, resolve_path_for_pid};
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+mod unsupported;
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+pub(crate) use unsupported::{get_dev_for_pid, resolve_path_for_pid};
@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+use crate::TtyError;
+
+/// Fallback for any target OS (OpenBSD, NetBSD, illumos, ...) we don't have
+/// a real backend for yet. Surfaces a clear error instead of failing to
+/// compile or silently returning bogus paths.
+pub(crate) fn get_dev_for_pid(_pid: i32) -> Result<i64, TtyError> {
+    Err(TtyError::UnsupportedPlatform)
+}
+
+pub(crate) fn resolve_path_for_pid(_pid: i32) -> Result<PathBuf, TtyError> {
+    Err(TtyError::UnsupportedPlatform)
+}